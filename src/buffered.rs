@@ -0,0 +1,78 @@
+//! Draining multiple pending HID reports in a single pass, instead of `read_data`'s one-report-
+//! per-call contract.
+
+use hidapi::HidDevice;
+
+use crate::{Kind, StreamDeckError, StreamDeckInput};
+use crate::util::parse_report;
+
+/// Drains every report currently queued on a device in one pass, instead of one round-trip per
+/// report.
+pub struct BufferedReader {
+    kind: Kind,
+    length: usize,
+    buf: Vec<u8>,
+    /// An error hit after some reports in a batch had already been parsed, surfaced on the next
+    /// call instead of discarding those reports.
+    pending_error: Option<StreamDeckError>,
+}
+
+impl BufferedReader {
+    /// Creates a reader for `kind`'s devices, whose reports are `length` bytes.
+    pub fn new(kind: Kind, length: usize) -> Self {
+        Self {
+            kind,
+            length,
+            buf: vec![0u8; length],
+            pending_error: None,
+        }
+    }
+
+    /// Switches `device` to non-blocking mode and reads until it has nothing left queued,
+    /// parsing each report and returning them in arrival order.
+    pub fn drain(&mut self, device: &HidDevice) -> Result<Vec<StreamDeckInput>, StreamDeckError> {
+        if let Some(err) = self.pending_error.take() {
+            return Err(err);
+        }
+
+        device.set_blocking_mode(false).map_err(StreamDeckError::HidError)?;
+
+        let mut inputs = vec![];
+
+        loop {
+            let read = match device.read(self.buf.as_mut_slice()) {
+                Ok(read) => read,
+                Err(e) => return self.fail_or_defer(inputs, StreamDeckError::HidError(e)),
+            };
+
+            if read == 0 {
+                break;
+            }
+
+            // Reused buffer may hold a shorter report than `length`; zero the tail so it can't
+            // be misread as part of this one.
+            if read < self.buf.len() {
+                self.buf[read..].fill(0);
+            }
+
+            match parse_report(&self.kind, &self.buf) {
+                Ok(Some(input)) => inputs.push(input),
+                Ok(None) => {}
+                Err(e) => return self.fail_or_defer(inputs, e),
+            }
+        }
+
+        Ok(inputs)
+    }
+
+    /// Surfaces `err` immediately if nothing has been collected yet this call; otherwise returns
+    /// what's already been parsed and defers `err` to the next call.
+    fn fail_or_defer(&mut self, inputs: Vec<StreamDeckInput>, err: StreamDeckError) -> Result<Vec<StreamDeckInput>, StreamDeckError> {
+        if inputs.is_empty() {
+            Err(err)
+        } else {
+            self.pending_error = Some(err);
+            Ok(inputs)
+        }
+    }
+}
@@ -0,0 +1,217 @@
+//! Watches for Stream Deck devices being plugged in or unplugged.
+
+use std::collections::HashMap;
+use std::io;
+
+use hidapi::{DeviceInfo, HidApi};
+
+use crate::Kind;
+
+/// Elgato's USB vendor id, shared by every Stream Deck model.
+const ELGATO_VENDOR_ID: u16 = 0x0fd9;
+
+/// A connect/disconnect notification from [DeviceMonitor].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DeviceEvent {
+    /// A device matching `kind` appeared, identified by its USB serial number.
+    Connected { serial: String, kind: Kind },
+    /// The device with this serial number disappeared.
+    Disconnected { serial: String },
+}
+
+fn io_err(e: impl std::error::Error + Send + Sync + 'static) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, e)
+}
+
+fn identify(device: &DeviceInfo) -> Option<(String, Kind)> {
+    if device.vendor_id() != ELGATO_VENDOR_ID {
+        return None;
+    }
+
+    let kind = Kind::from_pid(device.product_id())?;
+    let serial = device.serial_number()?.to_string();
+
+    Some((serial, kind))
+}
+
+enum Backend {
+    #[cfg(all(target_os = "linux", feature = "udev"))]
+    Udev(linux::UdevMonitor),
+    Polling(HidApi),
+}
+
+/// Watches for Elgato devices being attached or removed. Backed by udev on Linux when the
+/// `udev` feature is enabled, otherwise by periodically re-enumerating with [HidApi].
+pub struct DeviceMonitor {
+    known: HashMap<String, Kind>,
+    backend: Backend,
+}
+
+impl DeviceMonitor {
+    /// Creates a monitor and takes its first snapshot of currently connected devices.
+    ///
+    /// The initial snapshot is not reported as [DeviceEvent::Connected]s; call [Self::poll]
+    /// afterwards to learn about devices that connect or disconnect from then on.
+    pub fn new() -> io::Result<Self> {
+        let api = HidApi::new().map_err(io_err)?;
+        let known = Self::snapshot(&api);
+        let backend = Self::pick_backend(api)?;
+
+        Ok(Self { known, backend })
+    }
+
+    #[cfg(all(target_os = "linux", feature = "udev"))]
+    fn pick_backend(api: HidApi) -> io::Result<Backend> {
+        match linux::UdevMonitor::new() {
+            Ok(udev) => Ok(Backend::Udev(udev)),
+            // No udev socket available (e.g. no permissions) - poll instead rather than fail.
+            Err(_) => Ok(Backend::Polling(api)),
+        }
+    }
+
+    #[cfg(not(all(target_os = "linux", feature = "udev")))]
+    fn pick_backend(api: HidApi) -> io::Result<Backend> {
+        Ok(Backend::Polling(api))
+    }
+
+    fn snapshot(api: &HidApi) -> HashMap<String, Kind> {
+        api.device_list().filter_map(identify).collect()
+    }
+
+    /// Checks for devices that have connected or disconnected since the last call (or since
+    /// [Self::new], on the first call).
+    pub fn poll(&mut self) -> io::Result<Vec<DeviceEvent>> {
+        match &mut self.backend {
+            Backend::Polling(api) => {
+                api.refresh_devices().map_err(io_err)?;
+                let current = Self::snapshot(api);
+                Ok(self.diff(current))
+            }
+
+            #[cfg(all(target_os = "linux", feature = "udev"))]
+            Backend::Udev(udev) => Ok(udev
+                .poll()
+                .into_iter()
+                .filter(|event| self.apply(event))
+                .collect()),
+        }
+    }
+
+    /// Replaces `known` with `current`, returning the [DeviceEvent]s for whatever changed.
+    fn diff(&mut self, current: HashMap<String, Kind>) -> Vec<DeviceEvent> {
+        let mut events = vec![];
+
+        for serial in self.known.keys() {
+            if !current.contains_key(serial) {
+                events.push(DeviceEvent::Disconnected { serial: serial.clone() });
+            }
+        }
+
+        for (serial, kind) in &current {
+            if !self.known.contains_key(serial) {
+                events.push(DeviceEvent::Connected { serial: serial.clone(), kind: *kind });
+            }
+        }
+
+        self.known = current;
+        events
+    }
+
+    /// Applies a single event from an event-driven backend to `known`, returning whether it was
+    /// a real change (and so should be reported) rather than a duplicate.
+    fn apply(&mut self, event: &DeviceEvent) -> bool {
+        match event {
+            DeviceEvent::Connected { serial, kind } => self.known.insert(serial.clone(), *kind).is_none(),
+            DeviceEvent::Disconnected { serial } => self.known.remove(serial).is_some(),
+        }
+    }
+}
+
+#[cfg(all(target_os = "linux", feature = "udev"))]
+mod linux {
+    //! Event-driven monitoring via a udev `MonitorSocket` on the `usb` subsystem.
+
+    use std::io;
+    use std::os::unix::io::{AsRawFd, RawFd};
+
+    use udev::{EventType, MonitorBuilder};
+
+    use super::{DeviceEvent, ELGATO_VENDOR_ID};
+    use crate::Kind;
+
+    /// An fd-backed udev monitor yielding [DeviceEvent]s as the kernel reports them.
+    pub struct UdevMonitor {
+        socket: udev::MonitorSocket,
+    }
+
+    impl UdevMonitor {
+        pub fn new() -> io::Result<Self> {
+            let socket = MonitorBuilder::new()?.match_subsystem("usb")?.listen()?;
+            set_nonblocking(socket.as_raw_fd())?;
+
+            Ok(Self { socket })
+        }
+
+        /// Raw fd for registering this monitor with an epoll/async-fd reactor.
+        pub fn as_raw_fd(&self) -> RawFd {
+            self.socket.as_raw_fd()
+        }
+
+        /// Drains events already queued on the socket without blocking for more.
+        pub fn poll(&mut self) -> Vec<DeviceEvent> {
+            let mut events = vec![];
+
+            while let Some(event) = self.socket.next() {
+                let device = event.device();
+
+                let vendor_id = device
+                    .attribute_value("idVendor")
+                    .and_then(|v| v.to_str())
+                    .and_then(|v| u16::from_str_radix(v, 16).ok());
+
+                if vendor_id != Some(ELGATO_VENDOR_ID) {
+                    continue;
+                }
+
+                let serial = match device.attribute_value("serial").and_then(|v| v.to_str()) {
+                    Some(serial) => serial.to_string(),
+                    None => continue,
+                };
+
+                match event.event_type() {
+                    EventType::Add => {
+                        let kind = device
+                            .attribute_value("idProduct")
+                            .and_then(|v| v.to_str())
+                            .and_then(|v| u16::from_str_radix(v, 16).ok())
+                            .and_then(Kind::from_pid);
+
+                        if let Some(kind) = kind {
+                            events.push(DeviceEvent::Connected { serial, kind });
+                        }
+                    }
+
+                    EventType::Remove => events.push(DeviceEvent::Disconnected { serial }),
+
+                    _ => {}
+                }
+            }
+
+            events
+        }
+    }
+
+    fn set_nonblocking(fd: RawFd) -> io::Result<()> {
+        let flags = unsafe { libc::fcntl(fd, libc::F_GETFL, 0) };
+        if flags < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let result = unsafe { libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK) };
+        if result < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,175 @@
+//! A pluggable source of [StreamDeckInput], so reading can be backed by something other than a
+//! real [HidDevice] in tests and simulations.
+
+use std::collections::VecDeque;
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use hidapi::{HidDevice, HidError};
+
+use crate::{Kind, StreamDeckError, StreamDeckEvent, StreamDeckInput};
+use crate::util::{parse_report, read_data};
+
+/// Something that can produce [StreamDeckInput]s, implemented for a real [HidDevice] by
+/// [HidInputSource] and by [VirtualStreamDeck] so tests and simulations don't need real hardware.
+pub trait InputSource {
+    /// Reads and parses the next input, blocking up to `timeout` (or indefinitely if `None`).
+    /// Unlike [read_data], which does not block at all when given `None`, this waits for input.
+    fn read_input(&self, timeout: Option<Duration>) -> Result<StreamDeckInput, StreamDeckError>;
+}
+
+/// A real Stream Deck, read via [read_data] and [parse_report] exactly as before.
+pub struct HidInputSource<'a> {
+    pub kind: Kind,
+    pub device: &'a HidDevice,
+    pub length: usize,
+}
+
+impl<'a> InputSource for HidInputSource<'a> {
+    fn read_input(&self, timeout: Option<Duration>) -> Result<StreamDeckInput, StreamDeckError> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+
+        loop {
+            let remaining = match deadline {
+                Some(deadline) => {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    if remaining.is_zero() {
+                        return Err(StreamDeckError::HidError(HidError::HidApiError {
+                            message: "HidInputSource read timed out".to_string(),
+                        }));
+                    }
+                    Some(remaining)
+                }
+                None => None,
+            };
+
+            let data = read_data(self.device, self.length, remaining)
+                .map_err(StreamDeckError::HidError)?;
+
+            if let Some(input) = parse_report(&self.kind, &data)? {
+                return Ok(input);
+            }
+        }
+    }
+}
+
+/// A scripted Stream Deck for tests and macro-record/replay tooling; queue input with the
+/// `push_*` methods and read it back through [InputSource].
+pub struct VirtualStreamDeck {
+    kind: Kind,
+    queue: Mutex<VecDeque<StreamDeckInput>>,
+    has_input: Condvar,
+}
+
+impl VirtualStreamDeck {
+    /// Creates an empty virtual device of the given `kind`.
+    pub fn new(kind: Kind) -> Self {
+        Self {
+            kind,
+            queue: Mutex::new(VecDeque::new()),
+            has_input: Condvar::new(),
+        }
+    }
+
+    /// The device kind this virtual device is simulating.
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    /// Queues a full button-state report, as if every key in `states` was just pressed/released.
+    pub fn push_button_states(&self, states: Vec<bool>) {
+        self.queue_input(StreamDeckInput::ButtonStateChange(states));
+    }
+
+    /// Queues an encoder twist of `ticks[i]` for encoder `i` (negative is counter-clockwise).
+    pub fn push_encoder_twist(&self, ticks: Vec<i8>) {
+        self.queue_input(StreamDeckInput::EncoderTwist(ticks));
+    }
+
+    /// Queues a momentary touch at `(x, y)`.
+    pub fn push_touch(&self, x: u16, y: u16) {
+        self.queue_input(StreamDeckInput::TouchScreenEvent(StreamDeckEvent::TouchScreenPress(x, y)));
+    }
+
+    /// Queues a long-press at `(x, y)`.
+    pub fn push_long_press(&self, x: u16, y: u16) {
+        self.queue_input(StreamDeckInput::TouchScreenEvent(StreamDeckEvent::TouchScreenLongPress(x, y)));
+    }
+
+    /// Queues a swipe gesture from `start` to `end`.
+    pub fn push_swipe(&self, start: (u16, u16), end: (u16, u16)) {
+        self.queue_input(StreamDeckInput::TouchScreenEvent(StreamDeckEvent::TouchScreenSwipe(start, end)));
+    }
+
+    fn queue_input(&self, input: StreamDeckInput) {
+        self.queue.lock().unwrap().push_back(input);
+        self.has_input.notify_one();
+    }
+}
+
+impl InputSource for VirtualStreamDeck {
+    /// Pops the next queued input, waiting for one if the queue is empty (indefinitely if
+    /// `timeout` is `None`).
+    fn read_input(&self, timeout: Option<Duration>) -> Result<StreamDeckInput, StreamDeckError> {
+        let mut queue = self.queue.lock().unwrap();
+
+        match timeout {
+            None => {
+                while queue.is_empty() {
+                    queue = self.has_input.wait(queue).unwrap();
+                }
+            }
+
+            Some(timeout) => {
+                let deadline = Instant::now() + timeout;
+
+                while queue.is_empty() {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    if remaining.is_zero() {
+                        return Err(StreamDeckError::HidError(HidError::HidApiError {
+                            message: "VirtualStreamDeck read timed out".to_string(),
+                        }));
+                    }
+
+                    let (guard, _) = self.has_input.wait_timeout(queue, remaining).unwrap();
+                    queue = guard;
+                }
+            }
+        }
+
+        Ok(queue.pop_front().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn virtual_stream_deck_replays_queued_input_in_order() {
+        let deck = VirtualStreamDeck::new(Kind::Mini);
+
+        deck.push_button_states(vec![true, false, false]);
+        deck.push_encoder_twist(vec![1, -1]);
+        deck.push_touch(10, 20);
+
+        let timeout = Some(Duration::from_millis(50));
+
+        assert_eq!(
+            deck.read_input(timeout).unwrap(),
+            StreamDeckInput::ButtonStateChange(vec![true, false, false])
+        );
+        assert_eq!(deck.read_input(timeout).unwrap(), StreamDeckInput::EncoderTwist(vec![1, -1]));
+        assert_eq!(
+            deck.read_input(timeout).unwrap(),
+            StreamDeckInput::TouchScreenEvent(StreamDeckEvent::TouchScreenPress(10, 20))
+        );
+    }
+
+    #[test]
+    fn virtual_stream_deck_read_input_times_out_when_empty() {
+        let deck = VirtualStreamDeck::new(Kind::Mini);
+
+        assert!(deck.read_input(Some(Duration::from_millis(10))).is_err());
+    }
+}
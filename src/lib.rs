@@ -0,0 +1,15 @@
+pub mod util;
+
+mod buffered;
+mod hotplug;
+mod input_source;
+
+#[cfg(feature = "tokio")]
+mod stream;
+
+pub use buffered::BufferedReader;
+pub use hotplug::{DeviceEvent, DeviceMonitor};
+pub use input_source::{HidInputSource, InputSource, VirtualStreamDeck};
+
+#[cfg(feature = "tokio")]
+pub use stream::InputStream;
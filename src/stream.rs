@@ -0,0 +1,150 @@
+//! Async, pollable input stream, gated behind the `tokio` feature.
+
+#![cfg(feature = "tokio")]
+
+use std::collections::HashSet;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex as StdMutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use futures::Stream;
+use hidapi::HidDevice;
+use tokio::sync::Mutex;
+use tokio::task::spawn_blocking;
+
+use crate::{Kind, StreamDeckInput};
+use crate::util::{parse_report, read_data, sync_button_states};
+
+/// How long a single blocking read is allowed to wait before returning control to the
+/// worker loop, so the stream can be dropped/cancelled promptly.
+const POLL_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// A [Stream] of [StreamDeckInput] built on top of [read_data]. Each poll hands the device off
+/// to a `spawn_blocking` worker so the async runtime is never blocked on USB I/O.
+pub struct InputStream {
+    kind: Kind,
+    device: Arc<Mutex<HidDevice>>,
+    length: usize,
+    /// `(report_id, length)` for a button feature report; `None` disables resync.
+    button_report: Option<(u8, usize)>,
+    saved_states: Arc<StdMutex<HashSet<u8>>>,
+    /// Set when a read errors, cleared once reconciled.
+    desynced: Arc<AtomicBool>,
+    /// A resync event held back behind a real input read on the same poll.
+    queued: Option<StreamDeckInput>,
+    pending: Option<BoxFuture<'static, io::Result<Option<(StreamDeckInput, Option<StreamDeckInput>)>>>>,
+}
+
+impl InputStream {
+    /// Creates a new stream reading and parsing input from `device` as it arrives. `button_report`,
+    /// if given, enables [sync_button_states] resync after a dropped read.
+    pub fn new(kind: Kind, device: Arc<Mutex<HidDevice>>, length: usize, button_report: Option<(u8, usize)>) -> Self {
+        Self {
+            kind,
+            device,
+            length,
+            button_report,
+            saved_states: Arc::new(StdMutex::new(HashSet::new())),
+            desynced: Arc::new(AtomicBool::new(false)),
+            queued: None,
+            pending: None,
+        }
+    }
+
+    fn spawn_read(&self) -> BoxFuture<'static, io::Result<Option<(StreamDeckInput, Option<StreamDeckInput>)>>> {
+        let kind = self.kind;
+        let device = self.device.clone();
+        let length = self.length;
+        let button_report = self.button_report;
+        let saved_states = self.saved_states.clone();
+        let desynced = self.desynced.clone();
+
+        Box::pin(async move {
+            let device = device.lock_owned().await;
+
+            spawn_blocking(move || -> io::Result<Option<(StreamDeckInput, Option<StreamDeckInput>)>> {
+                let data = match read_data(&device, length, Some(POLL_TIMEOUT)) {
+                    Ok(data) => data,
+                    Err(e) => {
+                        desynced.store(true, Ordering::SeqCst);
+                        return Err(io::Error::new(io::ErrorKind::Other, e));
+                    }
+                };
+
+                let parsed = parse_report(&kind, &data)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{:?}", e)))?;
+
+                // Recovering from a prior error: resync instead of trusting this report. Stays
+                // desynced until the resync succeeds, and doesn't eat a real unrelated report.
+                let resynced = if desynced.load(Ordering::SeqCst) {
+                    match button_report {
+                        Some((report_id, report_len)) => {
+                            let mut saved_states = saved_states.lock().unwrap();
+                            sync_button_states(&device, &kind, report_id, report_len, &mut saved_states)
+                                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{:?}", e)))?;
+                            desynced.store(false, Ordering::SeqCst);
+
+                            let states = (0..kind.key_count())
+                                .map(|i| saved_states.contains(&i))
+                                .collect();
+
+                            Some(StreamDeckInput::ButtonStateChange(states))
+                        }
+                        None => None,
+                    }
+                } else {
+                    None
+                };
+
+                match (parsed, resynced) {
+                    (Some(StreamDeckInput::ButtonStateChange(_)), Some(reconciled)) => Ok(Some((reconciled, None))),
+                    (Some(real), resynced) => Ok(Some((real, resynced))),
+                    (None, Some(reconciled)) => Ok(Some((reconciled, None))),
+                    (None, None) => Ok(None),
+                }
+            })
+            .await
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
+        })
+    }
+}
+
+impl Stream for InputStream {
+    type Item = io::Result<StreamDeckInput>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if let Some(queued) = self.queued.take() {
+            return Poll::Ready(Some(Ok(queued)));
+        }
+
+        loop {
+            if self.pending.is_none() {
+                let fut = self.spawn_read();
+                self.pending = Some(fut);
+            }
+
+            let pending = self.pending.as_mut().unwrap();
+            match pending.as_mut().poll(cx) {
+                Poll::Ready(result) => {
+                    self.pending = None;
+
+                    match result {
+                        Ok(Some((input, deferred))) => {
+                            self.queued = deferred;
+                            return Poll::Ready(Some(Ok(input)));
+                        }
+                        // Timed-out poll with nothing to report yet, try again.
+                        Ok(None) => continue,
+                        Err(e) => return Poll::Ready(Some(Err(e))),
+                    }
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
@@ -134,6 +134,26 @@ pub enum StateChange {
     Remove(u8),
 }
 
+/// Routes a raw report to the matching parser, `None` if it carries no new input
+pub fn parse_report(kind: &Kind, data: &Vec<u8>) -> Result<Option<StreamDeckInput>, StreamDeckError> {
+    match data[0] {
+        0x1 => {
+            let states = read_button_states(kind, data);
+            if states.is_empty() {
+                Ok(None)
+            } else {
+                Ok(Some(StreamDeckInput::ButtonStateChange(states)))
+            }
+        }
+
+        0x2 => read_lcd_input(data).map(|event| Some(StreamDeckInput::TouchScreenEvent(event))),
+
+        0x3 => read_encoder_input(kind, data).map(Some),
+
+        _ => Ok(None),
+    }
+}
+
 /// Generate edge triggered events from a list of states
 pub fn state_diff(saved_states: &mut HashSet<u8>, new_states: &[bool]) -> Vec<StateChange> {
     new_states
@@ -155,3 +175,24 @@ pub fn state_diff(saved_states: &mut HashSet<u8>, new_states: &[bool]) -> Vec<St
         })
         .collect()
 }
+
+/// Resynchronizes `saved_states` against an authoritative feature-report snapshot, emitting the
+/// [StateChange]s needed to reconcile (Removes before Adds)
+pub fn sync_button_states(
+    device: &HidDevice,
+    kind: &Kind,
+    report_id: u8,
+    length: usize,
+    saved_states: &mut HashSet<u8>,
+) -> Result<Vec<StateChange>, StreamDeckError> {
+    let report = get_feature_report(device, report_id, length).map_err(StreamDeckError::HidError)?;
+    let states = read_button_states(kind, &report);
+
+    let changes = state_diff(saved_states, &states);
+    let (mut removes, adds): (Vec<_>, Vec<_>) = changes
+        .into_iter()
+        .partition(|change| matches!(change, StateChange::Remove(_)));
+
+    removes.extend(adds);
+    Ok(removes)
+}